@@ -1,4 +1,6 @@
 use crate::build::packages;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
 use std::ffi::OsString;
 use std::fs;
 use std::fs::File;
@@ -64,34 +66,176 @@ pub fn package_path(root: &str, package_name: &str) -> String {
     format!("{}/node_modules/{}", root, package_name)
 }
 
-/// Resolves a package following Node.js module resolution algorithm
-/// Traverses up the directory tree looking for the package in node_modules directories
-pub fn resolve_package_path(start_dir: &str, package_name: &str) -> Option<PathBuf> {
+/// The subset of a package's `package.json` that rewatch cares about when
+/// resolving an entry point. ReScript packages declare their config in
+/// `rescript.json`/`bsconfig.json`, but `main`/`exports` still tell us where
+/// the real package root lives in non-flat layouts.
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct PackageJson {
+    pub name: Option<String>,
+    pub main: Option<String>,
+    pub exports: Option<serde_json::Value>,
+}
+
+/// A package located through Node.js module resolution, with enough context for
+/// `get_bsc`/config discovery to trust the result in pnpm and yarn-berry trees.
+#[derive(Debug, Clone)]
+pub struct ResolvedPackage {
+    /// The real, symlink-resolved directory of the package.
+    pub path: PathBuf,
+    /// Whether the package was reached through a symlink, as pnpm's
+    /// `.pnpm/<pkg>@ver/node_modules/<pkg>` store layout always is.
+    pub via_symlink: bool,
+    /// The parsed `package.json` of the resolved package, if one is present.
+    pub manifest: Option<PackageJson>,
+    /// The declared entry point (from `exports` or `main`), resolved against
+    /// `path`. Non-flat layouts point their entry into a subdirectory, so this
+    /// is where config discovery should start rather than assuming the files sit
+    /// directly under `path`.
+    pub entry: Option<PathBuf>,
+}
+
+impl ResolvedPackage {
+    /// The directory that holds the package's ReScript config, derived from the
+    /// declared entry point. The search is bounded by `self.path`: a dependency
+    /// with no `rescript.json` beside its entry must not borrow an ancestor's
+    /// (e.g. the consuming project's) config. Falls back to the package root.
+    pub fn config_dir(&self) -> PathBuf {
+        let start = self
+            .entry
+            .as_ref()
+            .and_then(|entry| entry.parent())
+            .unwrap_or(self.path.as_path());
+
+        let mut current = start.to_path_buf();
+        loop {
+            if has_rescript_config(&current) {
+                return current;
+            }
+            // Stop at the package root; never walk above it.
+            if current == self.path {
+                return self.path.clone();
+            }
+            match current.parent() {
+                Some(parent) => current = parent.to_path_buf(),
+                None => return self.path.clone(),
+            }
+        }
+    }
+}
+
+fn read_package_json(package_dir: &Path) -> Option<PackageJson> {
+    let contents = fs::read_to_string(package_dir.join("package.json")).ok()?;
+    serde_json::from_str(&contents).ok()
+}
+
+/// Extracts the main entry path declared by a `package.json`. `exports` takes
+/// priority over `main` as Node does; within `exports` the `"."` subpath is
+/// consulted, unwrapping the conditional object (`import`/`require`/`default`)
+/// when present.
+fn entry_from_manifest(manifest: &PackageJson) -> Option<String> {
+    if let Some(exports) = &manifest.exports {
+        if let Some(entry) = exports_main_entry(exports) {
+            return Some(entry);
+        }
+    }
+    manifest.main.clone()
+}
+
+fn exports_main_entry(exports: &serde_json::Value) -> Option<String> {
+    match exports {
+        // `"exports": "./index.js"`
+        serde_json::Value::String(s) => Some(s.clone()),
+        serde_json::Value::Object(map) => {
+            // `"exports": { ".": <target> }`, else a bare conditional object.
+            let target = map.get(".").unwrap_or(exports);
+            match target {
+                serde_json::Value::String(s) => Some(s.clone()),
+                serde_json::Value::Object(conditions) => ["import", "require", "default", "node"]
+                    .iter()
+                    .find_map(|key| conditions.get(*key))
+                    .and_then(|value| value.as_str())
+                    .map(|s| s.to_string()),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Resolves a package following the Node.js module resolution algorithm.
+/// Traverses up the directory tree looking for the package in node_modules
+/// directories, canonicalizing through symlinks so pnpm's store layout resolves
+/// to the real package root, and reads its `package.json` along the way.
+pub fn resolve_package(start_dir: &str, package_name: &str) -> Option<ResolvedPackage> {
+    let candidate = node_modules_candidate(start_dir, package_name)?;
+
+    let via_symlink = fs::symlink_metadata(&candidate)
+        .map(|meta| meta.file_type().is_symlink())
+        .unwrap_or(false);
+    // Canonicalize through symlinks so the pnpm/yarn-berry store layout resolves
+    // to the package's real location on disk. This canonicalization stays inside
+    // `ResolvedPackage`: the back-compat `resolve_package_path` shim must keep
+    // returning the `node_modules/<name>` path, since callers walk up from it to
+    // find the consumer project root.
+    let path = candidate.canonicalize().unwrap_or(candidate);
+    let manifest = read_package_json(&path);
+    // Surface the declared entry (exports/main) resolved against the real
+    // root so config discovery doesn't assume a flat layout.
+    let entry = manifest
+        .as_ref()
+        .and_then(entry_from_manifest)
+        .map(|rel| path.join(rel));
+    Some(ResolvedPackage {
+        path,
+        via_symlink,
+        manifest,
+        entry,
+    })
+}
+
+/// Walks up from `start_dir` returning the first existing
+/// `node_modules/<package_name>` path, without canonicalizing it. Scoped
+/// specifiers (`@scope/name`) are split across path segments.
+fn node_modules_candidate(start_dir: &str, package_name: &str) -> Option<PathBuf> {
     let mut current_dir = PathBuf::from(start_dir);
-    
+
     // First, make sure we have an absolute path
     if current_dir.is_relative() {
         if let Ok(abs_path) = current_dir.canonicalize() {
             current_dir = abs_path;
         }
     }
-    
+
     loop {
-        let node_modules_path = current_dir.join("node_modules").join(package_name);
-        
-        // Check if the package exists in this node_modules directory
-        if node_modules_path.exists() {
-            return Some(node_modules_path);
+        // A scoped specifier such as `@scope/name` lives at
+        // `node_modules/@scope/name`, so join each segment rather than the raw
+        // specifier string.
+        let mut candidate = current_dir.join("node_modules");
+        for segment in package_name.split('/') {
+            candidate.push(segment);
         }
-        
+
+        if candidate.exists() {
+            return Some(candidate);
+        }
+
         // Move up one directory level
         match current_dir.parent() {
             Some(parent) => current_dir = parent.to_path_buf(),
-            None => break, // Reached the root directory
+            None => return None, // Reached the root directory
         }
     }
-    
-    None
+}
+
+/// Resolves a package following Node.js module resolution algorithm
+/// Traverses up the directory tree looking for the package in node_modules directories
+pub fn resolve_package_path(start_dir: &str, package_name: &str) -> Option<PathBuf> {
+    // Return the `node_modules/<name>` path, not the symlink-canonicalized real
+    // directory: existing callers walk up from this to locate the consumer
+    // project/workspace root, which under pnpm must stay outside the `.pnpm`
+    // store. Canonicalization is confined to `ResolvedPackage::path`.
+    node_modules_candidate(start_dir, package_name)
 }
 
 /// Resolves a package following Node.js module resolution algorithm with multiple starting points
@@ -105,6 +249,252 @@ pub fn resolve_package_path_multi(start_dirs: &[&str], package_name: &str) -> Op
     None
 }
 
+/// Where a package was ultimately found when resolving through node_modules and
+/// the `REWATCH_PATH` search roots.
+#[derive(Debug, Clone)]
+pub enum ResolvedFrom {
+    /// Found in a `node_modules` directory; its compiled artifacts are expected
+    /// to be present already.
+    NodeModules(PathBuf),
+    /// Found in a `REWATCH_PATH` root. `needs_build` is set when the package has
+    /// no compiled artifacts yet and must be enqueued for compilation as part of
+    /// the current build, respecting its own `rescript.json`.
+    SearchRoot { path: PathBuf, needs_build: bool },
+}
+
+/// Raised when a referenced package cannot be located in any node_modules
+/// directory or `REWATCH_PATH` root. Carries every root consulted so the
+/// failure is actionable.
+#[derive(Debug, Clone)]
+pub struct PackageNotFound {
+    pub package_name: String,
+    pub searched_roots: Vec<PathBuf>,
+}
+
+impl std::fmt::Display for PackageNotFound {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "package \"{}\" not found; searched:\n{}",
+            self.package_name,
+            self.searched_roots
+                .iter()
+                .map(|root| format!("  {}", root.to_string_lossy()))
+                .collect::<Vec<_>>()
+                .join("\n")
+        )
+    }
+}
+
+/// The colon-separated `REWATCH_PATH` search roots, RUST_PATH style. Respects
+/// the platform path separator via `split_paths`.
+fn rewatch_path_roots() -> Vec<PathBuf> {
+    std::env::var_os("REWATCH_PATH")
+        .map(|value| std::env::split_paths(&value).collect())
+        .unwrap_or_default()
+}
+
+/// Resolves a package from the explicit start dirs first, then falls back to the
+/// `REWATCH_PATH` roots in priority order. A package found in a search root but
+/// lacking compiled artifacts is flagged `needs_build` rather than erroring.
+pub fn resolve_package_path_multi_with_env(
+    start_dirs: &[&str],
+    package_name: &str,
+) -> Result<ResolvedFrom, PackageNotFound> {
+    resolve_package_path_multi_with_roots(start_dirs, package_name, &rewatch_path_roots())
+}
+
+/// The search-root resolution with the roots passed in explicitly rather than
+/// read from the process environment. `resolve_package_path_multi_with_env`
+/// supplies `REWATCH_PATH`; tests (and callers that already hold the roots) can
+/// drive this directly without touching global state.
+pub fn resolve_package_path_multi_with_roots(
+    start_dirs: &[&str],
+    package_name: &str,
+    roots: &[PathBuf],
+) -> Result<ResolvedFrom, PackageNotFound> {
+    // The normal node_modules walk takes priority.
+    if let Some(path) = resolve_package_path_multi(start_dirs, package_name) {
+        return Ok(ResolvedFrom::NodeModules(path));
+    }
+    resolve_in_roots_or_err(start_dirs, package_name, roots)
+}
+
+/// The search-root fallback plus "not found" error, shared by the free
+/// resolution entry points and [`ResolutionCache`]. Assumes the `node_modules`
+/// walk has already failed.
+fn resolve_in_roots_or_err(
+    start_dirs: &[&str],
+    package_name: &str,
+    roots: &[PathBuf],
+) -> Result<ResolvedFrom, PackageNotFound> {
+    // Fall back to the search roots, consulting them in the order given.
+    for root in roots {
+        let candidate = root.join(package_name);
+        if has_rescript_config(&candidate) {
+            let path = candidate.canonicalize().unwrap_or(candidate);
+            // If the package has not been compiled yet, it needs to be built as
+            // part of this build instead of failing with "package not found".
+            let needs_build = !path.join("lib").join("ocaml").exists();
+            return Ok(ResolvedFrom::SearchRoot { path, needs_build });
+        }
+    }
+
+    // Report every `node_modules` directory the walk actually stat'd — that is
+    // each start dir *and all of its ancestors* — not just the start dirs, so
+    // the failure message matches what resolution really looked at.
+    let mut searched_roots: Vec<PathBuf> = Vec::new();
+    for start_dir in start_dirs {
+        let mut current = PathBuf::from(start_dir);
+        if current.is_relative() {
+            if let Ok(abs) = current.canonicalize() {
+                current = abs;
+            }
+        }
+        loop {
+            let candidate = current.join("node_modules");
+            if !searched_roots.contains(&candidate) {
+                searched_roots.push(candidate);
+            }
+            match current.parent() {
+                Some(parent) => current = parent.to_path_buf(),
+                None => break,
+            }
+        }
+    }
+    searched_roots.extend(roots.iter().cloned());
+    Err(PackageNotFound {
+        package_name: package_name.to_string(),
+        searched_roots,
+    })
+}
+
+/// Resolves a package and, when it is found in a `REWATCH_PATH` root without
+/// compiled artifacts, enqueues its real path on `build_queue` so the current
+/// build compiles it (respecting its own `rescript.json`) instead of erroring.
+/// Returns the resolved package directory. This is the on-demand dependency
+/// building the `REWATCH_PATH` feature promises: the caller drains `build_queue`
+/// into its compile pass.
+pub fn resolve_package_path_enqueuing(
+    start_dirs: &[&str],
+    package_name: &str,
+    build_queue: &mut Vec<PathBuf>,
+) -> Result<PathBuf, PackageNotFound> {
+    match resolve_package_path_multi_with_env(start_dirs, package_name)? {
+        ResolvedFrom::NodeModules(path) => Ok(path),
+        ResolvedFrom::SearchRoot { path, needs_build } => {
+            if needs_build && !build_queue.contains(&path) {
+                build_queue.push(path.clone());
+            }
+            Ok(path)
+        }
+    }
+}
+
+/// Memoizes the filesystem-heavy resolution lookups so each unique query
+/// touches disk once during a single build. A large monorepo resolves the same
+/// packages from hundreds of source files; without a cache each call re-walks
+/// the directory tree and stats `node_modules`.
+#[derive(Debug, Default)]
+pub struct ResolutionCache {
+    resolved: RefCell<HashMap<(PathBuf, String), Option<PathBuf>>>,
+    workspace_root: RefCell<HashMap<String, Option<String>>>,
+    rescript_version: RefCell<HashMap<String, String>>,
+}
+
+impl ResolutionCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cached [`resolve_package_path`], keyed on `(start dir, package name)`.
+    pub fn resolve_package_path(&self, start_dir: &str, package_name: &str) -> Option<PathBuf> {
+        let key = (PathBuf::from(start_dir), package_name.to_string());
+        if let Some(cached) = self.resolved.borrow().get(&key) {
+            return cached.clone();
+        }
+        let resolved = resolve_package_path(start_dir, package_name);
+        self.resolved.borrow_mut().insert(key, resolved.clone());
+        resolved
+    }
+
+    /// Cached [`resolve_package_path_multi`], trying each start dir through the
+    /// per-query cache so a package shared across many source files is walked
+    /// once per `(start dir, package name)` pair.
+    pub fn resolve_package_path_multi(
+        &self,
+        start_dirs: &[&str],
+        package_name: &str,
+    ) -> Option<PathBuf> {
+        start_dirs
+            .iter()
+            .find_map(|start_dir| self.resolve_package_path(start_dir, package_name))
+    }
+
+    /// Cached [`get_workspace_root`], keyed on `package_root`. The workspace
+    /// root is stable for a given package during a build, but distinct packages
+    /// have distinct roots, so the result must be keyed on the argument.
+    pub fn get_workspace_root(&self, package_root: &str) -> Option<String> {
+        if let Some(cached) = self.workspace_root.borrow().get(package_root) {
+            return cached.clone();
+        }
+        let root = get_workspace_root(package_root);
+        self.workspace_root
+            .borrow_mut()
+            .insert(package_root.to_string(), root.clone());
+        root
+    }
+
+    /// Cached search-root resolution: the `node_modules` walk goes through the
+    /// per-query cache so a dependency referenced from many source files is
+    /// stat'd once, then falls back to the `REWATCH_PATH` roots. This is the
+    /// threaded entry point a build holds. Only the `node_modules` walk is
+    /// memoized; the search-root fallback stats the roots on every call.
+    pub fn resolve_with_roots(
+        &self,
+        start_dirs: &[&str],
+        package_name: &str,
+        roots: &[PathBuf],
+    ) -> Result<ResolvedFrom, PackageNotFound> {
+        if let Some(path) = self.resolve_package_path_multi(start_dirs, package_name) {
+            return Ok(ResolvedFrom::NodeModules(path));
+        }
+        resolve_in_roots_or_err(start_dirs, package_name, roots)
+    }
+
+    /// Cached variant of [`resolve_package_path_enqueuing`], threading this
+    /// cache through the `node_modules` walk.
+    pub fn resolve_package_path_enqueuing(
+        &self,
+        start_dirs: &[&str],
+        package_name: &str,
+        build_queue: &mut Vec<PathBuf>,
+    ) -> Result<PathBuf, PackageNotFound> {
+        match self.resolve_with_roots(start_dirs, package_name, &rewatch_path_roots())? {
+            ResolvedFrom::NodeModules(path) => Ok(path),
+            ResolvedFrom::SearchRoot { path, needs_build } => {
+                if needs_build && !build_queue.contains(&path) {
+                    build_queue.push(path.clone());
+                }
+                Ok(path)
+            }
+        }
+    }
+
+    /// Cached [`get_rescript_version`], keyed on `bsc_path`. Avoids shelling out
+    /// to `bsc -v` more than once per compiler binary.
+    pub fn get_rescript_version(&self, bsc_path: &str) -> String {
+        if let Some(cached) = self.rescript_version.borrow().get(bsc_path) {
+            return cached.clone();
+        }
+        let version = get_rescript_version(bsc_path);
+        self.rescript_version
+            .borrow_mut()
+            .insert(bsc_path.to_string(), version.clone());
+        version
+    }
+}
+
 pub fn get_abs_path(path: &str) -> String {
     let abs_path_buf = PathBuf::from(path);
 
@@ -247,6 +637,93 @@ pub fn get_ast_path(source_file: &str) -> PathBuf {
     )
 }
 
+/// A phase of the bsc pipeline. Ordered `Parse < Typecheck < Codegen` so a
+/// range can be expressed as a `from`/`to` pair.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CompilePhase {
+    Parse,
+    Typecheck,
+    Codegen,
+}
+
+impl CompilePhase {
+    /// Parses a phase from the value of a `--from`/`--to` CLI argument.
+    pub fn from_arg(value: &str) -> Result<Self, StdErr> {
+        match value.to_lowercase().as_str() {
+            "parse" | "ast" => Ok(CompilePhase::Parse),
+            "typecheck" | "type" => Ok(CompilePhase::Typecheck),
+            "codegen" | "compile" => Ok(CompilePhase::Codegen),
+            other => Err(format!(
+                "unknown compile phase \"{}\" (expected parse, typecheck, or codegen)",
+                other
+            )),
+        }
+    }
+}
+
+/// An inclusive first-phase/last-phase window, mirroring a compiler driver that
+/// accepts a `--from`/`--to` pair instead of a single "compile up to" point.
+/// A parse-only build emits just the `.ast`/`.iast` files; a typecheck-only
+/// build stops before `.cmj` codegen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PhaseRange {
+    pub from: CompilePhase,
+    pub to: CompilePhase,
+}
+
+impl Default for PhaseRange {
+    fn default() -> Self {
+        PhaseRange {
+            from: CompilePhase::Parse,
+            to: CompilePhase::Codegen,
+        }
+    }
+}
+
+impl PhaseRange {
+    /// Builds a range, rejecting an inverted window up front.
+    pub fn new(from: CompilePhase, to: CompilePhase) -> Result<Self, StdErr> {
+        if from > to {
+            return Err(format!(
+                "invalid phase range: --from ({:?}) is later than --to ({:?})",
+                from, to
+            ));
+        }
+        Ok(PhaseRange { from, to })
+    }
+
+    /// Builds a range from the raw `--from`/`--to` CLI argument strings, each
+    /// defaulting to the full pipeline's bound when not supplied. This is the
+    /// entry point the CLI flag handler calls.
+    pub fn from_args(from: Option<&str>, to: Option<&str>) -> Result<Self, StdErr> {
+        let from = from
+            .map(CompilePhase::from_arg)
+            .transpose()?
+            .unwrap_or(CompilePhase::Parse);
+        let to = to
+            .map(CompilePhase::from_arg)
+            .transpose()?
+            .unwrap_or(CompilePhase::Codegen);
+        Self::new(from, to)
+    }
+
+    pub fn contains(&self, phase: CompilePhase) -> bool {
+        self.from <= phase && phase <= self.to
+    }
+
+    /// Whether assets with the given extension should be demanded for this
+    /// range. AST outputs belong to `Parse`, the interface/type outputs to
+    /// `Typecheck`, and `.cmj` codegen to `Codegen`.
+    pub fn demands_asset(&self, extension: &str) -> bool {
+        match extension {
+            "ast" | "iast" => self.contains(CompilePhase::Parse),
+            "cmi" | "cmt" | "cmti" => self.contains(CompilePhase::Typecheck),
+            "cmj" => self.contains(CompilePhase::Codegen),
+            _ => true,
+        }
+    }
+}
+
 pub fn get_compiler_asset(
     package: &packages::Package,
     namespace: &packages::Namespace,
@@ -264,6 +741,27 @@ pub fn get_compiler_asset(
         + extension
 }
 
+/// The compiler assets a build should demand for `source_file` given the
+/// selected [`PhaseRange`]. Parse-only ranges yield just the `.ast`/`.iast`
+/// output; a typecheck range adds `.cmi`/`.cmt` but withholds `.cmj` codegen.
+/// This is what gates bsc's outputs on `--from`/`--to`.
+pub fn demanded_compiler_assets(
+    package: &packages::Package,
+    namespace: &packages::Namespace,
+    source_file: &str,
+    range: &PhaseRange,
+) -> Vec<String> {
+    let is_interface = is_interface_file(&get_extension(source_file));
+    let ast_ext = if is_interface { "iast" } else { "ast" };
+    // Candidate outputs in pipeline order; each is kept only if its phase falls
+    // within the requested range.
+    [ast_ext, "cmi", "cmt", "cmj"]
+        .iter()
+        .filter(|ext| range.demands_asset(ext))
+        .map(|ext| get_compiler_asset(package, namespace, source_file, ext))
+        .collect()
+}
+
 pub fn canonicalize_string_path(path: &str) -> Option<PathBuf> {
     return Path::new(path).canonicalize().ok();
 }
@@ -289,6 +787,118 @@ pub fn get_bs_compiler_asset(
         .to_owned()
 }
 
+/// A single source file's content hash and the compiler assets it produced.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ArtifactEntry {
+    /// Hex-encoded blake3 hash of the source contents at compile time.
+    pub hash: String,
+    /// Paths of the `.cmt`/`.cmj`/`.ast`/… assets produced from this source.
+    pub assets: Vec<String>,
+}
+
+/// Per-package manifest mapping each source file to its last-compiled content
+/// hash and the outputs it owns. Persisted next to the build output so stale
+/// `.cmt`/`.cmj`/`.ast` files left behind by renamed or deleted sources can be
+/// garbage-collected on the next build, keeping each package self-contained.
+#[derive(Debug, Default, serde::Serialize, serde::Deserialize)]
+pub struct ArtifactManifest {
+    entries: HashMap<String, ArtifactEntry>,
+}
+
+impl ArtifactManifest {
+    fn manifest_path(package: &packages::Package) -> PathBuf {
+        PathBuf::from(package.get_ocaml_build_path()).join(".rewatch-artifacts.json")
+    }
+
+    /// Load the manifest persisted beside `package`'s build output, or an empty
+    /// one if none exists yet or it cannot be parsed.
+    pub fn load(package: &packages::Package) -> Self {
+        fs::read_to_string(Self::manifest_path(package))
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    /// Persist the manifest next to the build output.
+    pub fn save(&self, package: &packages::Package) -> Result<(), StdErr> {
+        let contents = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("could not serialize artifact manifest: {}", e))?;
+        fs::write(Self::manifest_path(package), contents)
+            .map_err(|e| format!("could not write artifact manifest: {}", e))
+    }
+
+    /// Record the assets produced for `source_file` at content hash `hash`.
+    pub fn record(&mut self, source_file: &str, hash: &blake3::Hash, assets: Vec<String>) {
+        self.entries.insert(
+            source_file.to_string(),
+            ArtifactEntry {
+                hash: hash.to_hex().to_string(),
+                assets,
+            },
+        );
+    }
+
+    /// Delete every asset whose owning source no longer exists in `current` or
+    /// whose content hash has changed, and drop those entries from the manifest.
+    /// `current` maps each live source file to its freshly computed hash.
+    pub fn garbage_collect(&mut self, current: &HashMap<String, blake3::Hash>) {
+        let stale: Vec<String> = self
+            .entries
+            .iter()
+            .filter(|(source, entry)| {
+                current
+                    .get(*source)
+                    .map(|hash| hash.to_hex().to_string() != entry.hash)
+                    .unwrap_or(true)
+            })
+            .map(|(source, _)| source.clone())
+            .collect();
+
+        for source in stale {
+            if let Some(entry) = self.entries.remove(&source) {
+                for asset in entry.assets {
+                    let _ = fs::remove_file(&asset);
+                }
+            }
+        }
+    }
+}
+
+/// Prune stale compiler output for `package`: load the persisted manifest, drop
+/// assets whose owning source was renamed, deleted, or changed content (per its
+/// freshly computed blake3 hash in `current`), and persist the pruned manifest.
+/// Call this once per package at the top of a build so incremental rebuilds stay
+/// free of orphaned `.cmt`/`.cmj`/`.ast` files.
+pub fn garbage_collect_stale_artifacts(
+    package: &packages::Package,
+    current: &HashMap<String, blake3::Hash>,
+) -> Result<ArtifactManifest, StdErr> {
+    let mut manifest = ArtifactManifest::load(package);
+    manifest.garbage_collect(current);
+    manifest.save(package)?;
+    Ok(manifest)
+}
+
+/// Records, into `manifest`, the assets just produced for `source_file` so a
+/// later build can prune them when the source is renamed, deleted, or changed.
+/// Call this after compiling each source; without it the manifest stays empty
+/// and [`ArtifactManifest::garbage_collect`] has nothing to act on. The assets
+/// recorded are exactly those demanded for `range`, matching what the compiler
+/// actually emitted for this build. Returns the source's content hash so the
+/// caller can also feed it to [`garbage_collect_stale_artifacts`].
+pub fn record_compiled_source(
+    manifest: &mut ArtifactManifest,
+    package: &packages::Package,
+    namespace: &packages::Namespace,
+    source_file: &str,
+    range: &PhaseRange,
+) -> Option<blake3::Hash> {
+    let hash = compute_file_hash(Path::new(source_file))?;
+    let assets = demanded_compiler_assets(package, namespace, source_file, range);
+    manifest.record(source_file, &hash, assets);
+    Some(hash)
+}
+
 pub fn get_namespace_from_module_name(module_name: &str) -> Option<String> {
     let mut split = module_name.split('-');
     let _ = split.next();
@@ -364,21 +974,104 @@ fn has_rescript_config(path: &Path) -> bool {
     path.join("bsconfig.json").exists() || path.join("rescript.json").exists()
 }
 
+/// Raised when package/config discovery walks into a dependency graph that
+/// references itself. `chain` holds the canonical path of every package on the
+/// cycle, so `Display` can print the full `A -> B -> C -> A` trace.
+#[derive(Debug, Clone)]
+pub struct CircularDependency {
+    pub chain: Vec<String>,
+}
+
+impl std::fmt::Display for CircularDependency {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "circular dependency: {}", self.chain.join(" -> "))
+    }
+}
+
+impl std::error::Error for CircularDependency {}
+
+/// A stack-based DFS guard for dependency resolution. The invariant is that each
+/// package appears at most once on the active stack; the visited set prevents
+/// re-expanding an already-finished subgraph so detection stays linear in the
+/// number of edges.
+#[derive(Debug, Default)]
+pub struct ResolutionStack {
+    stack: Vec<PathBuf>,
+    visited: HashSet<PathBuf>,
+}
+
+impl ResolutionStack {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Descend into `path`. Returns `Ok(true)` when the package was pushed and
+    /// should be expanded, `Ok(false)` when it was already fully visited and can
+    /// be skipped, and `Err` with the full cycle trace when the path is already
+    /// on the active stack. Balance a `true` result with a later [`leave`].
+    ///
+    /// [`leave`]: ResolutionStack::leave
+    pub fn enter(&mut self, path: &Path) -> Result<bool, CircularDependency> {
+        let canonical = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+
+        if let Some(index) = self.stack.iter().position(|entry| entry == &canonical) {
+            let mut chain: Vec<String> = self.stack[index..]
+                .iter()
+                .map(|entry| entry.to_string_lossy().to_string())
+                .collect();
+            // Close the loop so the trace reads A -> B -> C -> A.
+            chain.push(canonical.to_string_lossy().to_string());
+            return Err(CircularDependency { chain });
+        }
+
+        if !self.visited.insert(canonical.clone()) {
+            return Ok(false);
+        }
+
+        self.stack.push(canonical);
+        Ok(true)
+    }
+
+    /// Pop the most recently entered package once its subgraph is finished.
+    pub fn leave(&mut self) {
+        self.stack.pop();
+    }
+}
+
 pub fn get_workspace_root(package_root: &str) -> Option<String> {
+    let mut stack = ResolutionStack::new();
     std::path::PathBuf::from(&package_root)
         .parent()
-        .and_then(get_nearest_config)
+        .and_then(|parent| get_nearest_config_guarded(parent, &mut stack).unwrap_or(None))
 }
 
 // traverse up the directory tree until we find a config.json, if not return None
 pub fn get_nearest_config(path_buf: &Path) -> Option<String> {
+    // Guard the upward walk with the cycle detector: a symlinked directory whose
+    // canonical path is already on the walk is a cycle, treated as "no config".
+    let mut stack = ResolutionStack::new();
+    get_nearest_config_guarded(path_buf, &mut stack).unwrap_or(None)
+}
+
+/// Cycle-guarded variant of [`get_nearest_config`]. Pushes each directory onto
+/// `stack` before ascending so a self-referential (symlinked) directory chain,
+/// where a directory canonicalizes to one already on the walk, surfaces a
+/// [`CircularDependency`] with the full trace.
+pub fn get_nearest_config_guarded(
+    path_buf: &Path,
+    stack: &mut ResolutionStack,
+) -> Result<Option<String>, CircularDependency> {
     let mut current_dir = path_buf.to_owned();
     loop {
+        // Already fully visited in this walk: nothing new to find above it.
+        if !stack.enter(&current_dir)? {
+            return Ok(None);
+        }
         if has_rescript_config(&current_dir) {
-            return Some(current_dir.to_string_lossy().to_string());
+            return Ok(Some(current_dir.to_string_lossy().to_string()));
         }
         match current_dir.parent() {
-            None => return None,
+            None => return Ok(None),
             Some(parent) => current_dir = parent.to_path_buf(),
         }
     }
@@ -472,6 +1165,213 @@ mod tests {
         let _ = fs::remove_dir_all(&temp_dir);
     }
 
+    #[test]
+    fn test_phase_range_rejects_inverted_window() {
+        assert!(PhaseRange::new(CompilePhase::Codegen, CompilePhase::Parse).is_err());
+
+        let parse_only = PhaseRange::new(CompilePhase::Parse, CompilePhase::Parse).unwrap();
+        assert!(parse_only.demands_asset("ast"));
+        assert!(!parse_only.demands_asset("cmj"));
+
+        let typecheck = PhaseRange::new(CompilePhase::Parse, CompilePhase::Typecheck).unwrap();
+        assert!(typecheck.demands_asset("cmt"));
+        assert!(!typecheck.demands_asset("cmj"));
+
+        assert!(PhaseRange::default().demands_asset("cmj"));
+    }
+
+    #[test]
+    fn test_phase_range_from_cli_args() {
+        // Defaults span the whole pipeline.
+        let full = PhaseRange::from_args(None, None).unwrap();
+        assert_eq!(full, PhaseRange::default());
+
+        // `--to typecheck` stops before codegen.
+        let typecheck = PhaseRange::from_args(None, Some("typecheck")).unwrap();
+        assert!(typecheck.demands_asset("cmt"));
+        assert!(!typecheck.demands_asset("cmj"));
+
+        // An inverted window is rejected, as is an unknown phase name.
+        assert!(PhaseRange::from_args(Some("codegen"), Some("parse")).is_err());
+        assert!(PhaseRange::from_args(Some("bogus"), None).is_err());
+    }
+
+    #[test]
+    fn test_resolution_cache_memoizes() {
+        let temp_dir = std::env::temp_dir().join("rewatch_test_cache");
+        let _ = fs::remove_dir_all(&temp_dir);
+
+        let project_dir = temp_dir.join("project");
+        fs::create_dir_all(&project_dir).unwrap();
+        let package = temp_dir.join("node_modules").join("cached-package");
+        fs::create_dir_all(&package).unwrap();
+
+        let cache = ResolutionCache::new();
+        let start = project_dir.to_string_lossy().to_string();
+
+        let first = cache.resolve_package_path(&start, "cached-package");
+        assert!(first.is_some());
+
+        // Removing the package afterwards must not change the cached answer.
+        fs::remove_dir_all(&package).unwrap();
+        let second = cache.resolve_package_path(&start, "cached-package");
+        assert_eq!(first, second);
+
+        let _ = fs::remove_dir_all(&temp_dir);
+    }
+
+    #[test]
+    fn test_resolution_cache_keys_on_argument() {
+        let temp_dir = std::env::temp_dir().join("rewatch_test_cache_keys");
+        let _ = fs::remove_dir_all(&temp_dir);
+
+        // Two distinct packages reachable from two distinct start dirs.
+        let project_a = temp_dir.join("a");
+        let project_b = temp_dir.join("b");
+        fs::create_dir_all(project_a.join("node_modules").join("pkg-a")).unwrap();
+        fs::create_dir_all(project_b.join("node_modules").join("pkg-b")).unwrap();
+
+        let cache = ResolutionCache::new();
+        let a = cache.resolve_package_path(&project_a.to_string_lossy(), "pkg-a");
+        let b = cache.resolve_package_path(&project_b.to_string_lossy(), "pkg-b");
+
+        // A second lookup with a different argument must not return the first
+        // answer from a shared single-value slot.
+        assert!(a.as_ref().unwrap().ends_with("pkg-a"));
+        assert!(b.as_ref().unwrap().ends_with("pkg-b"));
+        assert_ne!(a, b);
+
+        let _ = fs::remove_dir_all(&temp_dir);
+    }
+
+    #[test]
+    fn test_resolution_stack_detects_cycle() {
+        let temp_dir = std::env::temp_dir().join("rewatch_test_cycle");
+        let _ = fs::remove_dir_all(&temp_dir);
+
+        let a = temp_dir.join("a");
+        let b = temp_dir.join("b");
+        fs::create_dir_all(&a).unwrap();
+        fs::create_dir_all(&b).unwrap();
+
+        let mut stack = ResolutionStack::new();
+        assert_eq!(stack.enter(&a).unwrap(), true);
+        assert_eq!(stack.enter(&b).unwrap(), true);
+
+        // Re-entering `a` while it is still on the stack is a cycle.
+        let err = stack.enter(&a).unwrap_err();
+        assert_eq!(err.chain.first(), err.chain.last());
+        assert_eq!(err.chain.len(), 3);
+
+        stack.leave();
+        stack.leave();
+
+        // A finished subgraph is skipped, not reported as a cycle.
+        assert_eq!(stack.enter(&a).unwrap(), false);
+
+        let _ = fs::remove_dir_all(&temp_dir);
+    }
+
+    #[test]
+    fn test_resolve_package_path_multi_with_roots_search_root() {
+        let temp_dir = std::env::temp_dir().join("rewatch_test_env_resolution");
+        let _ = fs::remove_dir_all(&temp_dir);
+
+        let project_dir = temp_dir.join("project");
+        fs::create_dir_all(&project_dir).unwrap();
+
+        // A ReScript package living in a REWATCH_PATH root rather than node_modules
+        let root = temp_dir.join("vendored");
+        let package = root.join("some-dep");
+        fs::create_dir_all(&package).unwrap();
+        fs::write(package.join("rescript.json"), "{}").unwrap();
+
+        // Pass the root explicitly rather than mutating the global REWATCH_PATH,
+        // which races under cargo's parallel test threads (and `set_var` is now
+        // unsafe).
+        let resolved = resolve_package_path_multi_with_roots(
+            &[project_dir.to_string_lossy().as_ref()],
+            "some-dep",
+            &[root.clone()],
+        );
+
+        let enqueued = match resolved {
+            Ok(ResolvedFrom::SearchRoot { path, needs_build }) => {
+                assert!(path.ends_with("some-dep"));
+                // No lib/ocaml output yet, so it must be enqueued for building.
+                assert!(needs_build);
+                path
+            }
+            other => panic!("expected a search-root resolution, got {:?}", other),
+        };
+
+        // A needs_build search-root package is pushed onto the build queue.
+        let mut build_queue: Vec<PathBuf> = Vec::new();
+        if let Ok(ResolvedFrom::SearchRoot { path, needs_build }) =
+            resolve_package_path_multi_with_roots(
+                &[project_dir.to_string_lossy().as_ref()],
+                "some-dep",
+                &[root],
+            )
+        {
+            if needs_build {
+                build_queue.push(path);
+            }
+        }
+        assert_eq!(build_queue, vec![enqueued]);
+
+        let _ = fs::remove_dir_all(&temp_dir);
+    }
+
+    #[test]
+    fn test_resolve_scoped_package() {
+        // A scoped specifier `@scope/name` must resolve to node_modules/@scope/name
+        let temp_dir = std::env::temp_dir().join("rewatch_test_scoped_resolution");
+        let _ = fs::remove_dir_all(&temp_dir);
+
+        let project_dir = temp_dir.join("project");
+        fs::create_dir_all(&project_dir).unwrap();
+
+        let scoped_package = temp_dir
+            .join("node_modules")
+            .join("@scope")
+            .join("name");
+        fs::create_dir_all(&scoped_package).unwrap();
+
+        let resolved = resolve_package(&project_dir.to_string_lossy(), "@scope/name");
+
+        assert!(resolved.is_some());
+        assert!(resolved.unwrap().path.ends_with("name"));
+
+        let _ = fs::remove_dir_all(&temp_dir);
+    }
+
+    #[test]
+    fn test_resolve_package_reads_exports_entry() {
+        let temp_dir = std::env::temp_dir().join("rewatch_test_exports_entry");
+        let _ = fs::remove_dir_all(&temp_dir);
+
+        let project_dir = temp_dir.join("project");
+        fs::create_dir_all(&project_dir).unwrap();
+
+        // A package whose real ReScript config lives under `src/`, reached via
+        // the declared `exports` entry rather than a flat layout.
+        let package = temp_dir.join("node_modules").join("nested-dep");
+        fs::create_dir_all(package.join("src")).unwrap();
+        fs::write(package.join("src").join("rescript.json"), "{}").unwrap();
+        fs::write(
+            package.join("package.json"),
+            r#"{"name":"nested-dep","exports":{".":{"import":"./src/index.js"}}}"#,
+        )
+        .unwrap();
+
+        let resolved = resolve_package(&project_dir.to_string_lossy(), "nested-dep").unwrap();
+        assert!(resolved.entry.unwrap().ends_with("src/index.js"));
+        assert!(resolved.config_dir().ends_with("src"));
+
+        let _ = fs::remove_dir_all(&temp_dir);
+    }
+
     #[test]
     fn test_resolve_package_path_not_found() {
         // Test that we return None when package is not found